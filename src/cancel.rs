@@ -0,0 +1,31 @@
+//! A shared flag used to cooperatively cancel an in-flight search.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A cheaply `Clone`-able flag that asks in-flight work to stop.
+///
+/// Cancellation is cooperative: nothing is force-killed. Holders of a clone just
+/// check [`CancelToken::is_cancelled`] at natural stopping points (the top of
+/// [`crate::run`], between directory-walker iterations, before picking up a queued
+/// job) and return early once it is set.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a new, not-yet-cancelled token.
+    pub fn new() -> CancelToken {
+        CancelToken(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// Flips the token, asking every holder to stop at its next check.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether [`CancelToken::cancel`] has been called on this token or any
+    /// of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,144 @@
+//! A standalone `.gitignore`/`.ignore` engine, so ignore rules are respected even
+//! outside an actual git repository (see [`crate::run_dir_with_ignore`]).
+//!
+//! Each directory contributes an [`IgnoreScope`]: an ordered list of compiled glob
+//! patterns and their polarity (plain rule vs `!`-negated), anchored to that
+//! directory. An [`IgnoreStack`] layers the scopes from the search root down to the
+//! current directory, so nested ignore files can override their ancestors', matching
+//! git's own precedence.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// One compiled rule parsed from a single line of an ignore file.
+struct IgnoreRule {
+    matcher: GlobMatcher,
+    /// `!`-prefixed rules re-include a path a previous rule excluded.
+    negate: bool,
+    /// Trailing-`/` rules only ever match directories.
+    dir_only: bool,
+}
+
+/// The rules loaded from `.gitignore`/`.ignore` in one directory, anchored to it.
+struct IgnoreScope {
+    dir: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreScope {
+    /// Loads and compiles `.gitignore` and `.ignore` from `dir`, if either is present.
+    /// Returns `None` when `dir` contributes no rules of its own.
+    fn load(dir: &Path) -> Option<IgnoreScope> {
+        let mut rules = Vec::new();
+
+        for name in [".gitignore", ".ignore"] {
+            let Ok(contents) = fs::read_to_string(dir.join(name)) else {
+                continue;
+            };
+
+            rules.extend(contents.lines().filter_map(parse_rule));
+        }
+
+        if rules.is_empty() {
+            None
+        } else {
+            Some(IgnoreScope { dir: dir.to_path_buf(), rules })
+        }
+    }
+
+    /// Whether the *last* rule in this scope matching `path` excludes (`true`) or
+    /// re-includes (`false`) it; `None` if nothing in this scope matches at all.
+    fn last_match(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+
+        self.rules
+            .iter()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .rfind(|rule| rule.matcher.is_match(relative))
+            .map(|rule| !rule.negate)
+    }
+}
+
+/// The chain of [`IgnoreScope`]s from the search root down to (and including) one
+/// directory, outermost first. Checking scopes in that order and keeping the last
+/// match gives nested ignore files priority over their ancestors', as git does.
+#[derive(Clone, Default)]
+pub struct IgnoreStack(Vec<Arc<IgnoreScope>>);
+
+impl IgnoreStack {
+    /// An empty stack, as used at the search root before its own ignore file (if any)
+    /// has been loaded.
+    pub fn new() -> IgnoreStack {
+        IgnoreStack(Vec::new())
+    }
+
+    /// Returns a new stack with `dir`'s own ignore rules layered on top of this one,
+    /// if `dir` has any. Call this once per directory, with the stack that was
+    /// already in effect for it, to get the stack its *children* should be checked
+    /// against.
+    pub fn enter(&self, dir: &Path) -> IgnoreStack {
+        match IgnoreScope::load(dir) {
+            None => self.clone(),
+            Some(scope) => {
+                let mut scopes = self.0.clone();
+                scopes.push(Arc::new(scope));
+                IgnoreStack(scopes)
+            }
+        }
+    }
+
+    /// Whether `path` is ignored: the last matching rule across every scope in the
+    /// stack wins, root to leaf.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.0
+            .iter()
+            .filter_map(|scope| scope.last_match(path, is_dir))
+            .next_back()
+            .unwrap_or(false)
+    }
+}
+
+/// Parses one line of a `.gitignore`/`.ignore` file into a compiled rule. Returns
+/// `None` for blank lines and comments.
+fn parse_rule(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let negate = line.starts_with('!');
+    let line = if negate { &line[1..] } else { line };
+
+    let dir_only = line.ends_with('/');
+    let line = line.strip_suffix('/').unwrap_or(line);
+
+    // A pattern with a `/` anywhere in it (a leading one, or one before the last
+    // segment) is anchored to the directory the ignore file lives in, like git's; one
+    // without is unanchored and matches at any depth under it, so we spell that out as
+    // a leading `**/` for the glob compiler. The anchoring check has to run before the
+    // leading `/` is stripped, or a root-anchored pattern like `/build` would look
+    // unanchored and match `build` at every depth instead of just at the root.
+    let anchored = line.contains('/');
+    let pattern = if anchored {
+        line.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{line}")
+    };
+
+    // Git never lets a bare `*` cross a directory boundary, so the glob has to be
+    // built with `literal_separator` on or `foo*` would also match `foo/bar`.
+    let glob = GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .ok()?;
+
+    Some(IgnoreRule {
+        matcher: glob.compile_matcher(),
+        negate,
+        dir_only,
+    })
+}
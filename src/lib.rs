@@ -6,6 +6,12 @@
 use std::fs;
 use std::error::Error;
 use std::env;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use colored::{ColoredString, Colorize};
 
@@ -13,9 +19,22 @@ use clap::ArgMatches;
 
 use git2::Repository;
 
+use regex::Regex;
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+
 pub mod thread_pool;
 use thread_pool::ThreadPool;
 
+pub mod cancel;
+use cancel::CancelToken;
+
+pub mod printer;
+use printer::{FileMatches, LineMatch};
+
+pub mod ignore;
+use ignore::IgnoreStack;
+
 /// Configuration built from the matched arguments.
 #[derive(Clone)]
 pub struct Config {
@@ -27,13 +46,31 @@ pub struct Config {
     pub original_path: String,
     /// Ignore case while looking for matches
     pub ignore_case: bool,
+    /// Compiled pattern to use instead of literal `query` matching, set via `--regex`/`-e`.
+    ///
+    /// When `None`, searches fall back to the cheaper literal `match_indices` path.
+    pub regex: Option<Regex>,
+    /// Extra literal patterns to search for alongside `query`, supplied via repeated
+    /// `-p`/`--pattern`.
+    pub patterns: Vec<String>,
+    /// Aho–Corasick automaton over `query` and `patterns`, built once when there is
+    /// more than one literal pattern to search for, so all of them can be matched in
+    /// a single pass over each line. Takes priority over `regex` when both are set,
+    /// since `--pattern` only makes sense for literal multi-pattern search. Built
+    /// ASCII-case-insensitively when `ignore_case` is set; unlike the other search
+    /// paths, non-ASCII letters still have to match case exactly.
+    pub automaton: Option<AhoCorasick>,
     /// Search in hidden files and directories
     pub hidden_files: bool,
-    /// TODO: by default will ignore patterns on a .gitignore.
-    /// This option forces the search on these patterns
+    /// By default, directory searches skip whatever `.gitignore`/`.ignore` files say
+    /// to ignore (via an actual git repo if `file_path` is in one, or our own
+    /// standalone parser otherwise). This option forces the search onto those
+    /// patterns too.
     pub force_git: bool,
     /// Amount of threads for searching. Default value: 6
     pub thread_count: usize,
+    /// Flipped to stop an in-flight search early, e.g. from a SIGINT handler.
+    pub cancel: CancelToken,
 }
 
 impl Config {
@@ -42,9 +79,14 @@ impl Config {
     /// # Panics
     ///
     /// For now, the method to obtain the original_path can fail
+    ///
+    /// # Errors
+    ///
+    /// Fails if `--regex` is given and `query` is not a valid regular expression, or
+    /// if `--pattern` is given and the patterns can't be compiled into an automaton.
     pub fn build(
         matches: ArgMatches,
-    ) -> Result<Config, &'static str> {
+    ) -> Result<Config, String> {
         let ignore_case = matches.get_flag("ic");
 
         let hidden_files = matches.get_flag("hidden_files");
@@ -56,6 +98,37 @@ impl Config {
             None => unreachable!("clap should check this"),
         };
 
+        let regex = if matches.get_flag("regex") {
+            let pattern = if ignore_case {
+                format!("(?i){query}")
+            } else {
+                query.clone()
+            };
+
+            Some(Regex::new(&pattern).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+
+        let patterns: Vec<String> = matches
+            .get_many::<String>("pattern")
+            .map(|vals| vals.map(|arg| arg.to_owned()).collect())
+            .unwrap_or_default();
+
+        let automaton = if !patterns.is_empty() {
+            let mut all_patterns = vec![query.clone()];
+            all_patterns.extend(patterns.iter().cloned());
+
+            Some(
+                AhoCorasickBuilder::new()
+                    .ascii_case_insensitive(ignore_case)
+                    .build(all_patterns)
+                    .map_err(|e| e.to_string())?,
+            )
+        } else {
+            None
+        };
+
         // if there's no file path, search in whole directory
         let file_path = match matches.get_one::<String>("path") {
             Some(arg) => arg.to_owned(),
@@ -67,29 +140,44 @@ impl Config {
             None => unreachable!("default value is 6"),
         };
 
-        Ok(Config { 
+        Ok(Config {
             original_path: env::current_dir().unwrap().to_str().unwrap().to_string(),
             query,
             file_path,
             ignore_case,
+            regex,
+            patterns,
+            automaton,
             hidden_files,
             force_git,
             thread_count,
+            cancel: CancelToken::new(),
         })
     }
 }
 
-/// Searches a **file** with the given configuration.
+/// Searches a **file** with the given configuration and sends any matches found to
+/// `printer` as a single [`FileMatches`], instead of printing them directly.
+///
+/// Returns immediately, doing nothing, if `config.cancel` has already been cancelled.
 ///
 /// # Panics
 ///
 /// The file path in [`Config`] should be a file and not a directory.
 ///
 /// For searching a directory recursively you should use [`run_dir`].
-pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+pub fn run(config: &Config, printer: &Sender<FileMatches>) -> Result<(), Box<dyn Error>> {
+    if config.cancel.is_cancelled() {
+        return Ok(());
+    }
+
     let contents = fs::read_to_string(&config.file_path)?;
 
-    let results = if config.ignore_case {
+    let results = if let Some(automaton) = &config.automaton {
+        search_multi_pattern(automaton, &contents)
+    } else if let Some(regex) = &config.regex {
+        search_regex(regex, &contents)
+    } else if config.ignore_case {
         search_case_insensitive(&config.query, &contents)
     } else {
         search(&config.query, &contents)
@@ -97,114 +185,294 @@ pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
 
     if !results.is_empty() {
         let path = std::path::Path::new(&config.file_path);
-        let filename = path.to_str().unwrap().strip_prefix(&config.original_path);
-
-        let mut output = format!("{}\n", filename.unwrap().purple());
-        for (indices, (line_number, line)) in results.iter() {
-            output.push_str(&format!("{: >4}: ", (line_number+1).to_string().yellow()));
-
-            let chunks = split_by_matches(line, indices.to_owned(), config.query.len());
-            for str in chunks.iter() {
-                output.push_str(&format!("{str}"));
-            }
-            output.push('\n');
-        }
-        println!("{output}");
+        let raw = path.to_str().unwrap();
+        // Falls back to the raw path when it isn't under original_path, e.g. a
+        // single-file search given a relative path or one outside the launch cwd.
+        let filename = raw.strip_prefix(&config.original_path).unwrap_or(raw);
+
+        let lines = results
+            .into_iter()
+            .map(|(spans, (line_number, line))| LineMatch {
+                line_number,
+                line: line.to_string(),
+                spans,
+            })
+            .collect();
+
+        printer
+            .send(FileMatches {
+                path: filename.to_string(),
+                lines,
+            })
+            .unwrap_or_default();
     }
-    
+
     Ok(())
 }
 /// Searches a **directory** recursively with the given configuration.
-/// Respects the gitignore file if inside a git repo
-/// 
-/// Can fail if the given path can't be accessed by [`env::set_current_dir`]
-pub fn run_dir_with_git(git_repo: &Repository, config: &Config, pool: &ThreadPool) -> Result<(), Box<dyn Error>> {
-    env::set_current_dir(&config.file_path)?;
-    let entries = fs::read_dir(env::current_dir()?)?;
-
-    for entry in entries {
-        match entry {
-            Err(e) => eprintln!("entry error: {:?}", e),
-            Ok(entry) => {
-                let path = entry.path();
-                if git_repo.is_path_ignored(&path)? {
-                    continue;
-                }
+/// Respects the gitignore file if inside a git repo.
+///
+/// Directory discovery is parallelized across `config.thread_count` walker threads
+/// that share a work queue of pending directories (see `walk_directories` below); regular
+/// files found while walking are handed off to `pool` for searching, which sends its
+/// matches to `printer`.
+///
+/// # Errors
+///
+/// Fails if the given path can't be canonicalized.
+pub fn run_dir_with_git(git_repo: &Repository, config: &Config, pool: &ThreadPool, printer: &Sender<FileMatches>) -> Result<(), Box<dyn Error>> {
+    let root = fs::canonicalize(&config.file_path)?;
+    let git_repo_path = git_repo.path().to_path_buf();
 
-                let md = fs::metadata(&path)?;
+    walk_directories(root, config, pool, printer, IgnoreSource::Git(git_repo_path))
+}
 
-                let mut new_config = config.clone();
-                new_config.file_path = match path.to_str() {
-                    None => {
-                        eprintln!("path error");
-                        new_config.file_path
-                    }
-                    Some(str) => str.to_string(),
-                };
-
-                let filename = path.file_name().unwrap().to_str().unwrap();
-
-                if config.hidden_files || !filename.starts_with(".") {
-                    if md.is_dir(){
-                        run_dir_with_git(git_repo, &new_config, pool)?;
-                        env::set_current_dir("../")?;
-                    } else {
-                        pool.execute(move || {
-                            let _ = run(&new_config);
-                        });
-                    }
-                }
-            }
-        }
-    }
+/// Searches a **directory** recursively with the given configuration, respecting any
+/// `.gitignore`/`.ignore` files found while walking even though `file_path` isn't
+/// inside an actual git repository (see [`ignore::IgnoreStack`]).
+///
+/// Directory discovery is parallelized across `config.thread_count` walker threads
+/// that share a work queue of pending directories (see `walk_directories` below); regular
+/// files found while walking are handed off to `pool` for searching, which sends its
+/// matches to `printer`.
+///
+/// # Errors
+///
+/// Fails if the given path can't be canonicalized.
+pub fn run_dir_with_ignore(config: &Config, pool: &ThreadPool, printer: &Sender<FileMatches>) -> Result<(), Box<dyn Error>> {
+    let root = fs::canonicalize(&config.file_path)?;
 
-    Ok(())
+    walk_directories(root, config, pool, printer, IgnoreSource::Standalone)
 }
 
-/// Searches a **directory** recursively with the given configuration.
-/// 
-/// Can fail if the given path can't be accessed by [`env::set_current_dir`]
-pub fn run_dir(config: &Config, pool: &ThreadPool) -> Result<(), Box<dyn Error>> {
-    env::set_current_dir(&config.file_path)?;
-    let entries = fs::read_dir(env::current_dir()?)?;
-
-    for entry in entries {
-        match entry {
-            Err(e) => eprintln!("entry error: {:?}", e),
-            Ok(entry) => {
-                let path = entry.path();
-                let md = fs::metadata(&path)?;
-
-                let mut new_config = config.clone();
-                new_config.file_path = match path.to_str() {
-                    None => {
-                        eprintln!("path error");
-                        new_config.file_path
+/// Searches a **directory** recursively with the given configuration, ignoring any
+/// `.gitignore`/`.ignore` files (used for `--force_git`).
+///
+/// Directory discovery is parallelized across `config.thread_count` walker threads
+/// that share a work queue of pending directories (see `walk_directories` below); regular
+/// files found while walking are handed off to `pool` for searching, which sends its
+/// matches to `printer`.
+///
+/// # Errors
+///
+/// Fails if the given path can't be canonicalized.
+pub fn run_dir(config: &Config, pool: &ThreadPool, printer: &Sender<FileMatches>) -> Result<(), Box<dyn Error>> {
+    let root = fs::canonicalize(&config.file_path)?;
+
+    walk_directories(root, config, pool, printer, IgnoreSource::None)
+}
+
+/// Where [`walk_directories`] should get its ignore rules from, if anywhere.
+enum IgnoreSource {
+    /// Don't skip anything.
+    None,
+    /// Ask an open git repository, found at this path, what it ignores.
+    Git(PathBuf),
+    /// Parse `.gitignore`/`.ignore` files found while walking, independent of git.
+    Standalone,
+}
+
+/// One directory still waiting to be read, along with the ignore rules in effect for
+/// its *contents* (its own ignore file, if [`IgnoreSource::Standalone`], already
+/// layered on top of its ancestors').
+struct QueueEntry {
+    dir: PathBuf,
+    ignore: IgnoreStack,
+}
+
+/// Shared state for [`walk_directories`]: the queue of directories still waiting to be
+/// read, and a count of workers currently busy reading one. The walk is done once the
+/// queue is empty and no worker is busy, i.e. there is nothing left to discover.
+///
+/// Paired with a [`Condvar`] that idle workers wait on instead of spinning; it's
+/// notified whenever a directory is queued or a worker's `busy` count drops to zero, so
+/// an idle worker wakes up whenever either of those might let it make progress.
+struct WalkerState {
+    queue: VecDeque<QueueEntry>,
+    busy: usize,
+}
+
+/// Walks `root` and every directory under it using `config.thread_count` worker
+/// threads that pop absolute paths off a shared queue, `read_dir` them, push any
+/// subdirectories they find back onto the queue, and hand regular files to `pool`.
+///
+/// Workers never touch the process-wide current directory, so this is safe to run
+/// while `pool` is concurrently searching files on other threads. With
+/// [`IgnoreSource::Git`], each worker opens its own [`Repository`] handle (git2's
+/// `Repository` is not `Sync`) and skips paths the repo considers ignored. With
+/// [`IgnoreSource::Standalone`], each queued directory carries the [`IgnoreStack`]
+/// its entries should be checked against, extended with its own ignore file (if any)
+/// before its subdirectories are queued in turn.
+///
+/// Workers check `config.cancel` between directories and between entries of the
+/// directory they're reading, so a [`CancelToken::cancel`] call stops the walk quickly
+/// without waiting for the whole tree to be discovered.
+fn walk_directories(
+    root: PathBuf,
+    config: &Config,
+    pool: &ThreadPool,
+    printer: &Sender<FileMatches>,
+    ignore_source: IgnoreSource,
+) -> Result<(), Box<dyn Error>> {
+    let root_ignore = match &ignore_source {
+        IgnoreSource::Standalone => IgnoreStack::new().enter(&root),
+        IgnoreSource::None | IgnoreSource::Git(_) => IgnoreStack::new(),
+    };
+
+    let state = Mutex::new(WalkerState {
+        queue: VecDeque::from([QueueEntry { dir: root, ignore: root_ignore }]),
+        busy: 0,
+    });
+    let queued = Condvar::new();
+
+    let standalone = matches!(ignore_source, IgnoreSource::Standalone);
+    let git_repo_path = match ignore_source {
+        IgnoreSource::Git(path) => Some(path),
+        IgnoreSource::None | IgnoreSource::Standalone => None,
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..config.thread_count {
+            let state = &state;
+            let queued = &queued;
+            let git_repo_path = git_repo_path.clone();
+
+            scope.spawn(move || {
+                let git_repo = git_repo_path.map(|path| {
+                    Repository::open(path).expect("git repo should still be openable")
+                });
+
+                loop {
+                    if config.cancel.is_cancelled() {
+                        break;
                     }
-                    Some(str) => str.to_string(),
-                };
-
-                let filename = path.file_name().unwrap().to_str().unwrap();
-
-                if config.hidden_files || !filename.starts_with(".") {
-                    if md.is_dir(){
-                        run_dir(&new_config, pool)?;
-                        env::set_current_dir("../")?;
-                    } else {
-                        pool.execute(move || {
-                            let _ = run(&new_config);
-                        });
+
+                    let entry = {
+                        let mut guard = state.lock().unwrap();
+                        loop {
+                            if let Some(entry) = guard.queue.pop_front() {
+                                guard.busy += 1;
+                                break Some(entry);
+                            } else if guard.busy == 0 || config.cancel.is_cancelled() {
+                                break None;
+                            } else {
+                                // Nothing to do right now, but another worker is still
+                                // busy and might queue more directories; wait to be
+                                // woken instead of spinning. The timeout is just a
+                                // backstop so a cancellation flipped while we're asleep
+                                // is still noticed promptly.
+                                let (woken, _timeout) = queued
+                                    .wait_timeout(guard, Duration::from_millis(50))
+                                    .unwrap();
+                                guard = woken;
+                            }
+                        }
+                    };
+
+                    let QueueEntry { dir, ignore } = match entry {
+                        Some(entry) => entry,
+                        None => break,
+                    };
+
+                    let entries = match fs::read_dir(&dir) {
+                        Ok(entries) => entries,
+                        Err(e) => {
+                            eprintln!("entry error: {e:?}");
+                            state.lock().unwrap().busy -= 1;
+                            queued.notify_all();
+                            continue;
+                        }
+                    };
+
+                    for entry in entries {
+                        if config.cancel.is_cancelled() {
+                            break;
+                        }
+
+                        let entry = match entry {
+                            Ok(entry) => entry,
+                            Err(e) => {
+                                eprintln!("entry error: {e:?}");
+                                continue;
+                            }
+                        };
+
+                        let path = entry.path();
+                        let filename = path.file_name().unwrap().to_str().unwrap();
+
+                        if !config.hidden_files && filename.starts_with('.') {
+                            continue;
+                        }
+
+                        if let Some(git_repo) = &git_repo {
+                            if git_repo.is_path_ignored(&path).unwrap_or(false) {
+                                continue;
+                            }
+                        }
+
+                        let md = match fs::metadata(&path) {
+                            Ok(md) => md,
+                            Err(e) => {
+                                eprintln!("entry error: {e:?}");
+                                continue;
+                            }
+                        };
+
+                        if standalone && ignore.is_ignored(&path, md.is_dir()) {
+                            continue;
+                        }
+
+                        if md.is_dir() {
+                            let child_ignore = if standalone {
+                                ignore.enter(&path)
+                            } else {
+                                ignore.clone()
+                            };
+
+                            state.lock().unwrap().queue.push_back(QueueEntry {
+                                dir: path,
+                                ignore: child_ignore,
+                            });
+                            queued.notify_all();
+                        } else {
+                            let mut new_config = config.clone();
+                            new_config.file_path = match path.to_str() {
+                                None => {
+                                    eprintln!("path error");
+                                    new_config.file_path
+                                }
+                                Some(str) => str.to_string(),
+                            };
+
+                            let printer = printer.clone();
+                            pool.execute(move || {
+                                let _ = run(&new_config, &printer);
+                            });
+                        }
                     }
+
+                    state.lock().unwrap().busy -= 1;
+                    queued.notify_all();
                 }
-            }
+            });
         }
-    }
+    });
 
     Ok(())
 }
 
+/// The result of searching `contents`: one entry per matching line, each holding the
+/// `(start, end)` byte spans matched on that line alongside its 0-based line number and
+/// text. Shared by every `search*` function so they can all feed [`split_by_matches`]
+/// the same way regardless of which one produced the matches.
+pub type SearchResults<'a> = Vec<(Vec<(usize, usize)>, (usize, &'a str))>;
+
 /// Search for query (case sensitive) in contents.
 ///
+/// Matches are reported as `(start, end)` byte-offset spans into the line, rather
+/// than bare start indices, so that the caller doesn't have to assume a fixed match
+/// length (see [`search_regex`], where matches can vary in length).
+///
 /// # Example
 ///
 /// ```rust
@@ -215,26 +483,26 @@ pub fn run_dir(config: &Config, pool: &ThreadPool) -> Result<(), Box<dyn Error>>
 ///
 /// assert_eq!(
 ///     vec![
-///         (vec![4], (0, "I'm testing a case sensitive query")),
-///         (vec![14], (1, "THIS IS A TesTtestTest"))],
+///         (vec![(4, 8)], (0, "I'm testing a case sensitive query")),
+///         (vec![(14, 18)], (1, "THIS IS A TesTtestTest"))],
 ///     search(query, contents));
 /// ```
 pub fn search<'a>(
     query: &str,
     contents: &'a str)
--> Vec<(Vec<usize>, (usize, &'a str))>{
+-> SearchResults<'a> {
     contents
         .lines()
         .enumerate()
         .map(|(num, line)| {
-            let index_list: Vec<usize> = 
+            let spans: Vec<(usize, usize)> =
                 line
                     .match_indices(query)
-                    .map(|(index, _v)| index)
+                    .map(|(start, matched)| (start, start + matched.len()))
                     .collect();
-            (index_list, (num, line))
+            (spans, (num, line))
         })
-        .filter(|(index_list, _info)| !index_list.is_empty())
+        .filter(|(spans, _info)| !spans.is_empty())
         .collect()
 }
 
@@ -250,35 +518,145 @@ pub fn search<'a>(
 ///
 /// assert_eq!(
 ///     vec![
-///         (vec![4, 24], (0, "I'm teSTing a case sensitestive query")),
-///         (vec![10,14,18], (1, "THIS IS A TesTtestTest"))],
+///         (vec![(4, 8), (24, 28)], (0, "I'm teSTing a case sensitestive query")),
+///         (vec![(10, 14), (14, 18), (18, 22)], (1, "THIS IS A TesTtestTest"))],
 ///     search_case_insensitive(query, contents));
 /// ```
 pub fn search_case_insensitive<'a>(
     query: &str,
     contents: &'a str)
--> Vec<(Vec<usize>, (usize, &'a str))>{
+-> SearchResults<'a> {
     let query = query.to_lowercase();
 
     contents
         .lines()
         .enumerate()
-        .map(|(num, line)| { 
-            let index_list: Vec<usize> = 
-                line
-                    .to_lowercase()
-                    .match_indices(&query)
-                    .map(|(index, _v)| index)
+        .map(|(num, line)| (case_insensitive_spans(&query, line), (num, line)))
+        .filter(|(spans, _info)| !spans.is_empty())
+        .collect()
+}
+
+/// Finds every case-insensitive, non-overlapping occurrence of `query` (already
+/// lowercased) in `line`, as byte spans into `line` itself.
+///
+/// Matching char-by-char against `line` directly, rather than lowercasing the whole
+/// line and running `match_indices` on that, avoids spans landing on the wrong bytes
+/// (or off a char boundary) when lowercasing changes a character's byte length, as it
+/// does for `İ` (U+0130).
+fn case_insensitive_spans(query: &str, line: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let query_chars: Vec<char> = query.chars().collect();
+    let line_chars: Vec<(usize, char)> = line.char_indices().collect();
+
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i + query_chars.len() <= line_chars.len() {
+        let is_match = query_chars
+            .iter()
+            .enumerate()
+            .all(|(j, &qc)| line_chars[i + j].1.to_lowercase().eq(qc.to_lowercase()));
+
+        if is_match {
+            let start = line_chars[i].0;
+            let end = line_chars
+                .get(i + query_chars.len())
+                .map(|&(idx, _)| idx)
+                .unwrap_or(line.len());
+            spans.push((start, end));
+            i += query_chars.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    spans
+}
+
+/// Search for a compiled `regex`, possibly matching variable-length spans, in contents.
+///
+/// # Example
+///
+/// ```rust
+/// use regex::Regex;
+/// use minigrep::search_regex;
+///
+/// let regex = Regex::new("fo+|bar").unwrap();
+/// let contents = "foo bar\nbaz";
+///
+/// assert_eq!(
+///     vec![(vec![(0, 3), (4, 7)], (0, "foo bar"))],
+///     search_regex(&regex, contents));
+/// ```
+pub fn search_regex<'a>(
+    regex: &Regex,
+    contents: &'a str)
+-> SearchResults<'a> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(num, line)| {
+            let spans: Vec<(usize, usize)> =
+                regex
+                    .find_iter(line)
+                    .map(|matched| (matched.start(), matched.end()))
                     .collect();
-            (index_list, (num, line))
+            (spans, (num, line))
         })
-        .filter(|(index_list, _info)| !index_list.is_empty())
+        .filter(|(spans, _info)| !spans.is_empty())
         .collect()
 }
 
-/// Splits a given line by the indices of a matched query
+/// Search for any of several literal patterns in contents in a single pass, using a
+/// compiled Aho–Corasick `automaton` (see [`Config::automaton`]).
+///
+/// Uses overlapping matching, so a pattern contained within another matched pattern
+/// (e.g. `"Test"` inside `"TestTest"`) is still reported on its own.
+///
+/// Spans are plain `(start, end)` pairs, the same as every other `search*` function, so
+/// they can all feed [`split_by_matches`] without it needing to know which one produced
+/// them. That means matches aren't tagged with which pattern produced them, so
+/// `split_by_matches` can't color different patterns differently; that would need a
+/// richer span type (e.g. `(start, end, pattern_id)`, from `matched.pattern()`) threaded
+/// through every `search*` function and `split_by_matches` alike.
+///
+/// # Example
+///
+/// ```rust
+/// use aho_corasick::AhoCorasick;
+/// use minigrep::search_multi_pattern;
+///
+/// let automaton = AhoCorasick::new(["foo", "bar"]).unwrap();
+/// let contents = "foo bar\nbaz";
+///
+/// assert_eq!(
+///     vec![(vec![(0, 3), (4, 7)], (0, "foo bar"))],
+///     search_multi_pattern(&automaton, contents));
+/// ```
+pub fn search_multi_pattern<'a>(
+    automaton: &AhoCorasick,
+    contents: &'a str)
+-> SearchResults<'a> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(num, line)| {
+            let spans: Vec<(usize, usize)> =
+                automaton
+                    .find_overlapping_iter(line)
+                    .map(|matched| (matched.start(), matched.end()))
+                    .collect();
+            (spans, (num, line))
+        })
+        .filter(|(spans, _info)| !spans.is_empty())
+        .collect()
+}
+
+/// Splits a given line by the `(start, end)` spans of a matched query
 /// and returns the line with the matches colored red.
-/// 
+///
 /// # Example
 ///
 /// ```rust
@@ -291,7 +669,7 @@ pub fn search_case_insensitive<'a>(
 /// let matches = search_case_insensitive(query, contents);
 /// let mut match_iter = matches.iter();
 ///
-/// let (indices, (_num, line)) = match_iter.next().unwrap().to_owned();
+/// let (spans, (_num, line)) = match_iter.next().unwrap().to_owned();
 /// assert_eq!(
 ///     vec![
 ///         "I'm ".normal(),
@@ -300,32 +678,34 @@ pub fn search_case_insensitive<'a>(
 ///         "test".red(),
 ///         "ive query".normal(),
 ///     ],
-///     split_by_matches(line, indices, query.len()));
+///     split_by_matches(line, spans));
 /// ```
 pub fn split_by_matches(
     line: &str,
-    indices: Vec<usize>,
-    query_len: usize)
+    mut spans: Vec<(usize, usize)>)
 -> Vec<ColoredString> {
-    let mut output: Vec<ColoredString> = Vec::new();
-    let mut match_str = line;
-    let mut real_index: usize = 0;
-
-    for index in indices.iter() {
-        let current_index = index - real_index;
-        real_index += current_index;
+    // search_multi_pattern reports overlapping matches, so spans aren't necessarily
+    // disjoint or in order; sort them and clamp each one to what the previous span
+    // hasn't already covered instead of assuming that up front.
+    spans.sort_unstable_by_key(|&(start, _)| start);
 
-        let (pre_match, rest) = match_str.split_at(current_index);
+    let mut output: Vec<ColoredString> = Vec::new();
+    let mut last_end = 0;
 
-        if !pre_match.is_empty() { output.push(pre_match.normal()); }
-        output.push(rest[..query_len].red());
+    for (start, end) in spans {
+        let start = start.max(last_end);
+        if start >= end {
+            // Fully covered by an earlier, overlapping span.
+            continue;
+        }
 
-        match_str =  {
-                real_index += query_len;
-                &rest[query_len..]
-        };
+        if start > last_end {
+            output.push(line[last_end..start].normal());
+        }
+        output.push(line[start..end].red());
+        last_end = end;
     }
-    output.push(match_str.normal());
+    output.push(line[last_end..].normal());
 
     output
 }
@@ -343,7 +723,7 @@ safe, fast, productive.
 Pick three.
 DUCT TAPE!";
 
-        assert_eq!(vec![(vec![15], (1, "safe, fast, productive."))], search(query, contents));
+        assert_eq!(vec![(vec![(15, 19)], (1, "safe, fast, productive."))], search(query, contents));
     }
 
     #[test]
@@ -357,14 +737,36 @@ Trust me.";
 
         assert_eq!(
             vec![
-                (vec![0],(0, "Rust:")),
-                (vec![12, 16],(2, "Pick three, rustrust.")),
-                (vec![1],(3, "Trust me."))
+                (vec![(0, 4)],(0, "Rust:")),
+                (vec![(12, 16), (16, 20)],(2, "Pick three, rustrust.")),
+                (vec![(1, 5)],(3, "Trust me."))
             ],
             search_case_insensitive(query, contents)
         );
     }
 
+    #[test]
+    fn regex_alternation() {
+        let regex = Regex::new("fo+|bar").unwrap();
+        let contents = "foo bar\nbaz";
+
+        assert_eq!(
+            vec![(vec![(0, 3), (4, 7)], (0, "foo bar"))],
+            search_regex(&regex, contents)
+        );
+    }
+
+    #[test]
+    fn multi_pattern() {
+        let automaton = AhoCorasick::new(["foo", "bar"]).unwrap();
+        let contents = "foo bar\nbaz";
+
+        assert_eq!(
+            vec![(vec![(0, 3), (4, 7)], (0, "foo bar"))],
+            search_multi_pattern(&automaton, contents)
+        );
+    }
+
     #[test]
     fn split_one_match() {
         let query = "duct";
@@ -375,12 +777,12 @@ Pick three.
 DUCT TAPE!";
 
         let res = search(query, contents);
-        let (indices, (_num, line)) = res.first().unwrap().to_owned();
+        let (spans, (_num, line)) = res.first().unwrap().to_owned();
 
 
         assert_eq!(
             vec!["safe, fast, pro".normal(), "duct".red(), "ive.".normal()],
-            split_by_matches(line, indices, query.len()));
+            split_by_matches(line, spans));
     }
 
     #[test]
@@ -405,11 +807,11 @@ Trust me.";
         let mut match_iter = matches.iter();
 
         match_iter.next();
-        let (indices, (_num, line)) = match_iter.next().unwrap().to_owned();
-        
+        let (spans, (_num, line)) = match_iter.next().unwrap().to_owned();
+
         assert_eq!(
             vec!["Pick three, ".normal(), "rust".red(), "rust".red(), ".".normal()],
-            split_by_matches(line, indices, query.len())
+            split_by_matches(line, spans)
         )
     }
 }
@@ -7,6 +7,7 @@ use clap::{Arg, Command};
 use git2::Repository;
 use minigrep::{
     Config,
+    printer::Printer,
     thread_pool::ThreadPool,
 };
 
@@ -41,6 +42,20 @@ fn main() {
             .help("Amount of threads to use. 6 is the default")
             .default_value("6")
         )
+        .arg(
+            Arg::new("regex")
+            .long("regex")
+            .short('e')
+            .help("Treats the query as a regular expression instead of a literal string")
+            .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("pattern")
+            .long("pattern")
+            .short('p')
+            .help("An additional literal pattern to search for alongside query (repeatable); all patterns are matched together in a single pass")
+            .action(clap::ArgAction::Append)
+        )
         .arg(
             Arg::new("query")
             .help("The string to search for matches")
@@ -70,24 +85,31 @@ fn main() {
         }
     };
 
-    let pool = ThreadPool::new(config.thread_count);
+    // Declared before `pool` so it's dropped after: `pool` joins every worker (so every
+    // search has finished sending its matches) before `printer` is joined in turn,
+    // which prints whatever is left in the queue and only then returns.
+    let printer = Printer::new();
+    let pool = ThreadPool::new(config.thread_count, config.cancel.clone());
+
+    let cancel = config.cancel.clone();
+    if let Err(e) = ctrlc::set_handler(move || cancel.cancel()) {
+        eprintln!("Error setting Ctrl-C handler: {e}");
+    }
 
-    let ret = 
+    let ret =
         if md.is_dir() {
-            if config.force_git{ 
-                minigrep::run_dir(&config, &pool) 
+            if config.force_git {
+                minigrep::run_dir(&config, &pool, &printer.sender())
             } else {
-                let git_repo = match Repository::open_from_env() {
-                    Ok(repo) => repo,
-                    Err(e) => {
-                        eprintln!("Error obtaining git repo: {e}");
-                        process::exit(2)
-                    }
-                };
-                minigrep::run_dir_with_git(&git_repo, &config, &pool)
+                match Repository::open_from_env() {
+                    Ok(git_repo) => minigrep::run_dir_with_git(&git_repo, &config, &pool, &printer.sender()),
+                    // Not in a git repo (or git2 couldn't open it): fall back to our
+                    // own .gitignore/.ignore parsing instead of searching everything.
+                    Err(_) => minigrep::run_dir_with_ignore(&config, &pool, &printer.sender()),
+                }
             }
         } else {
-            minigrep::run(&config)
+            minigrep::run(&config, &printer.sender())
         };
 
 
@@ -0,0 +1,91 @@
+//! A dedicated subsystem that owns stdout, so that results found by concurrent
+//! searches are printed one complete file block at a time instead of interleaving.
+
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use colored::Colorize;
+
+/// One matched line within a file, ready to be colored and printed.
+pub struct LineMatch {
+    /// 0-based line number, as returned by the `search*` functions.
+    pub line_number: usize,
+    /// The full line the match(es) were found on.
+    pub line: String,
+    /// `(start, end)` byte spans within `line` to highlight.
+    pub spans: Vec<(usize, usize)>,
+}
+
+/// Every matched line found in one file, in the order `run` found them.
+pub struct FileMatches {
+    /// Path of the file the matches were found in, with the directory minigrep was
+    /// launched from (`Config::original_path`) stripped from its front when it is
+    /// under it, or the path as given otherwise.
+    pub path: String,
+    /// The matched lines, in file order.
+    pub lines: Vec<LineMatch>,
+}
+
+/// Owns stdout and prints one complete, colored [`FileMatches`] block per message it
+/// receives, on a single dedicated thread, so multithreaded directory searches never
+/// garble each other's output.
+pub struct Printer {
+    sender: Option<Sender<FileMatches>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Printer {
+    /// Spawns the dedicated printer thread.
+    pub fn new() -> Printer {
+        let (sender, receiver) = mpsc::channel::<FileMatches>();
+
+        let handle = thread::spawn(move || {
+            for file_matches in receiver {
+                print_file_matches(&file_matches);
+            }
+        });
+
+        Printer {
+            sender: Some(sender),
+            handle: Some(handle),
+        }
+    }
+
+    /// Returns a cheaply `Clone`-able handle that `run` can send [`FileMatches`]
+    /// through, including from other threads (e.g. jobs on a [`crate::thread_pool::ThreadPool`]).
+    pub fn sender(&self) -> Sender<FileMatches> {
+        self.sender.as_ref().unwrap().clone()
+    }
+}
+
+impl Default for Printer {
+    fn default() -> Printer {
+        Printer::new()
+    }
+}
+
+impl Drop for Printer {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap_or(());
+        }
+    }
+}
+
+fn print_file_matches(file_matches: &FileMatches) {
+    let mut output = format!("{}\n", file_matches.path.purple());
+
+    for line_match in &file_matches.lines {
+        output.push_str(&format!("{: >4}: ", (line_match.line_number + 1).to_string().yellow()));
+
+        let chunks = crate::split_by_matches(&line_match.line, line_match.spans.clone());
+        for chunk in chunks.iter() {
+            output.push_str(&format!("{chunk}"));
+        }
+        output.push('\n');
+    }
+
+    println!("{output}");
+}
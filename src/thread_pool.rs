@@ -4,9 +4,10 @@
 //!
 //! ```rust
 //! use minigrep::thread_pool::ThreadPool;
+//! use minigrep::cancel::CancelToken;
 //!
-//! let thread_count = 4;    
-//! let pool = ThreadPool::new(thread_count);
+//! let thread_count = 4;
+//! let pool = ThreadPool::new(thread_count, CancelToken::new());
 //!
 //! let expensive_search_function = || { /* expensive stuff!!! */ };
 //!
@@ -18,11 +19,15 @@ use std::{
     thread,
 };
 
+use crate::cancel::CancelToken;
+
 /// Structure that handles creation of workers
 /// and communication
 pub struct ThreadPool {
     workers: Vec<Worker>,
     sender: Option<mpsc::Sender<Job>>,
+    receiver: Arc<Mutex<mpsc::Receiver<Job>>>,
+    cancel: CancelToken,
 }
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
@@ -30,12 +35,14 @@ type Job = Box<dyn FnOnce() + Send + 'static>;
 impl ThreadPool {
     /// Create a new ThreadPool.
     ///
-    /// The size is the number of threads in the pool.
+    /// The size is the number of threads in the pool. `cancel` is shared with the
+    /// workers: once it is set, they stop picking up new jobs, and [`ThreadPool::cancel`]
+    /// uses the same token to flip it from the outside.
     ///
     /// # Panics
     ///
     /// The `new` function will panic if the size is zero.
-    pub fn new(size: usize) -> ThreadPool {
+    pub fn new(size: usize, cancel: CancelToken) -> ThreadPool {
         assert!(size > 0);
 
         let (sender, receiver) = mpsc::channel();
@@ -45,24 +52,46 @@ impl ThreadPool {
         let mut workers = Vec::with_capacity(size);
 
         for _id in 0..size {
-            workers.push(Worker::new(Arc::clone(&receiver)));
+            workers.push(Worker::new(Arc::clone(&receiver), cancel.clone()));
         }
 
         ThreadPool {
             workers,
             sender: Some(sender),
+            receiver,
+            cancel,
         }
     }
 
     /// Send a function to be handled when available.
+    ///
+    /// Does nothing if the pool has already been [`ThreadPool::cancel`]ed.
     pub fn execute<F>(&self, function: F)
     where
         F: FnOnce() + Send + 'static,
     {
+        if self.cancel.is_cancelled() {
+            return;
+        }
+
         let job: Job = Box::new(function);
 
         self.sender.as_ref().unwrap().send(job).unwrap_or_default();
     }
+
+    /// Cancels the pool: flips the shared [`CancelToken`] so workers stop picking up
+    /// new jobs, then drains any jobs still sitting in the queue so a long run over a
+    /// huge tree can be stopped without waiting for it to finish or killing the process.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+
+        // An idle worker holds the receiver's lock while it blocks in `recv`, so only
+        // drain here when the lock is free; otherwise the workers pull the remaining
+        // jobs themselves and discard them unrun, since the token is now set.
+        if let Ok(receiver) = self.receiver.try_lock() {
+            while receiver.try_recv().is_ok() {}
+        }
+    }
 }
 
 impl Drop for ThreadPool {
@@ -82,7 +111,7 @@ struct Worker {
 }
 
 impl Worker {
-    fn new(receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Worker {
+    fn new(receiver: Arc<Mutex<mpsc::Receiver<Job>>>, cancel: CancelToken) -> Worker {
         let thread = thread::spawn(move || loop {
             let message = receiver
                 .lock()
@@ -91,7 +120,9 @@ impl Worker {
 
             match message {
                 Ok(job) => {
-                    job();
+                    if !cancel.is_cancelled() {
+                        job();
+                    }
                 }
                 Err(_) => {
                     break;